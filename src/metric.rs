@@ -11,7 +11,14 @@
 //! of these components can choose what metrics theyâ€™re interested in and also
 //! can add their own custom metrics without the need to maintain forks.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+pub mod family;
+#[cfg(feature = "metric")]
+pub mod info;
+#[cfg(feature = "metric")]
+pub mod registry;
+pub mod sink;
 
 /// Abstraction over the common metric operations.
 ///
@@ -65,6 +72,87 @@ impl Metric for () {
     }
 }
 
+/// Abstraction over a metric whose value can go both up and down.
+///
+/// Unlike [`Metric`], which models a monotonic counter, a `Gauge` represents a
+/// value that is expected to rise and fall over the lifetime of the process,
+/// such as the number of active vCPUs, the depth of a virtqueue or the amount
+/// of currently ballooned memory.
+pub trait Gauge {
+    /// Sets the gauge to `value`.
+    fn set(&self, value: i64);
+    /// Adds `value` to the current value.
+    fn add(&self, value: i64);
+    /// Increments by 1 unit the current value.
+    fn inc(&self) {
+        self.add(1);
+    }
+    /// Subtracts `value` from the current value.
+    fn dec(&self, value: i64);
+    /// Returns the current value of the gauge.
+    fn value(&self) -> i64;
+}
+
+/// A dummy `Gauge` implementation that can be used in components that do not
+/// expose metrics.
+///
+/// This mirrors the NOP [`Metric`] impl for `()`, allowing a gauge to be
+/// replaced with a NOP.
+impl Gauge for () {
+    fn set(&self, _: i64) {}
+
+    fn add(&self, _: i64) {}
+
+    fn dec(&self, _: i64) {}
+
+    fn value(&self) -> i64 {
+        0
+    }
+}
+
+/// A [`Gauge`] backed by an [`AtomicI64`].
+///
+/// # Example
+/// ```rust
+/// use vmm_sys_util::metric::{AtomicGauge, Gauge};
+///
+/// let queue_depth = AtomicGauge::new(0);
+/// queue_depth.add(3);
+/// queue_depth.inc();
+/// queue_depth.dec(2);
+/// assert_eq!(queue_depth.value(), 2);
+///
+/// queue_depth.set(10);
+/// assert_eq!(queue_depth.value(), 10);
+/// ```
+#[derive(Debug, Default)]
+pub struct AtomicGauge(AtomicI64);
+
+impl AtomicGauge {
+    /// Creates a new gauge initialized to `value`.
+    pub fn new(value: i64) -> Self {
+        AtomicGauge(AtomicI64::new(value))
+    }
+}
+
+impl Gauge for AtomicGauge {
+    fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    fn add(&self, value: i64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn dec(&self, value: i64) {
+        self.0.fetch_sub(value, Ordering::Relaxed);
+    }
+
+    fn value(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 impl Metric for AtomicUsize {
     /// Adds `value` to the current counter.
     fn add(&self, value: usize) {
@@ -76,3 +164,111 @@ impl Metric for AtomicUsize {
         self.load(Ordering::Relaxed)
     }
 }
+
+/// A `Metric` whose memory ordering is chosen at construction time, with an
+/// extra `store_max` operation for tracking a high-water mark.
+///
+/// The plain [`AtomicUsize`] impl hard-codes [`Ordering::Relaxed`], which is
+/// enough for a free-running counter. `OrderedMetric` is for the cases where a
+/// reader needs a happens-before relationship with other state, or where the
+/// interesting value is the maximum observed rather than a running total (peak
+/// queue depth, maximum latency).
+///
+/// `store` and `load` are taken as two separate orderings, rather than derived
+/// from one, because the derivation is lossy: a writer publishing state with
+/// `Ordering::Release` pairs with a reader doing an `Ordering::Acquire` load,
+/// but an `AtomicUsize` load rejects `Release` outright, so a single-ordering
+/// constructor would have to downgrade it to `Ordering::Relaxed` and quietly
+/// throw away the happens-before relationship the caller asked for. Pass
+/// `Ordering::Release` for `store` and `Ordering::Acquire` for `load` to get
+/// that relationship.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::Ordering;
+/// use vmm_sys_util::metric::{Metric, OrderedMetric};
+///
+/// let max_latency = OrderedMetric::new(Ordering::Release, Ordering::Acquire);
+/// max_latency.store_max(5);
+/// max_latency.store_max(2);
+/// max_latency.store_max(9);
+/// max_latency.store_max(7);
+/// assert_eq!(max_latency.count(), 9);
+/// ```
+pub struct OrderedMetric {
+    value: AtomicUsize,
+    store: Ordering,
+    load: Ordering,
+}
+
+impl OrderedMetric {
+    /// Creates a counter initialized to `0`, storing with `store` and loading
+    /// with `load`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `load` is [`Ordering::Release`] or [`Ordering::AcqRel`], see
+    /// [`OrderedMetric::with_value`].
+    pub fn new(store: Ordering, load: Ordering) -> Self {
+        OrderedMetric::with_value(0, store, load)
+    }
+
+    /// Creates a counter initialized to `value`, storing with `store` and
+    /// loading with `load`.
+    ///
+    /// # Panics
+    ///
+    /// `load` is used for every plain [`AtomicUsize::load`], which panics at
+    /// runtime for the store-only orderings [`Ordering::Release`] and
+    /// [`Ordering::AcqRel`]. Rather than let that panic surface later, deep
+    /// inside an unrelated `count`/`store_max` call, this constructor panics
+    /// immediately if `load` is one of them. Valid values for `load` are
+    /// [`Ordering::Relaxed`], [`Ordering::Acquire`] and [`Ordering::SeqCst`].
+    ///
+    /// ```rust,should_panic
+    /// use std::sync::atomic::Ordering;
+    /// use vmm_sys_util::metric::OrderedMetric;
+    ///
+    /// // `Release` is not a valid load ordering and is rejected immediately.
+    /// OrderedMetric::new(Ordering::Relaxed, Ordering::Release);
+    /// ```
+    pub fn with_value(value: usize, store: Ordering, load: Ordering) -> Self {
+        assert!(
+            !matches!(load, Ordering::Release | Ordering::AcqRel),
+            "OrderedMetric: `load` ordering must be Relaxed, Acquire or SeqCst, got {:?}",
+            load
+        );
+        OrderedMetric {
+            value: AtomicUsize::new(value),
+            store,
+            load,
+        }
+    }
+
+    /// Records `value` as the new counter value if it exceeds the current one,
+    /// so the counter tracks the maximum ever observed.
+    pub fn store_max(&self, value: usize) {
+        let mut current = self.value.load(self.load);
+        while value > current {
+            match self
+                .value
+                .compare_exchange_weak(current, value, self.store, self.load)
+            {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl Metric for OrderedMetric {
+    /// Adds `value` to the current counter.
+    fn add(&self, value: usize) {
+        self.value.fetch_add(value, self.store);
+    }
+
+    /// Returns current value of the counter.
+    fn count(&self) -> usize {
+        self.value.load(self.load)
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2020 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+//! Break a single logical metric down by label.
+//!
+//! A [`Family`] holds one child metric per distinct label set, creating
+//! children lazily the first time a label set is seen. This matches the way
+//! real VMMs need the same metric partitioned by device, queue or vCPU (for
+//! example `block_requests{device="vda"}`), while still being able to obtain a
+//! single total across all partitions via [`Family::aggregate`].
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::metric::Metric;
+
+/// A metric partitioned into children keyed by a label set `L`.
+///
+/// Children of type `M` are created on demand and then live for the lifetime
+/// of the family; they are never removed. `M` must implement [`Default`], which
+/// provides the zero-valued child created on first access.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::AtomicUsize;
+/// use vmm_sys_util::metric::Metric;
+/// use vmm_sys_util::metric::family::Family;
+///
+/// let requests: Family<String, AtomicUsize> = Family::new();
+/// requests.get_or_create(&"vda".to_string()).add(2);
+/// requests.get_or_create(&"vdb".to_string()).inc();
+/// requests.get_or_create(&"vda".to_string()).inc();
+///
+/// assert_eq!(requests.get_or_create(&"vda".to_string()).count(), 3);
+/// assert_eq!(requests.aggregate(), 4);
+/// ```
+pub struct Family<L, M> {
+    children: Mutex<HashMap<L, Box<M>>>,
+}
+
+impl<L, M> Default for Family<L, M> {
+    fn default() -> Self {
+        Family {
+            children: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<L, M> Family<L, M> {
+    /// Creates an empty family with no children.
+    pub fn new() -> Self {
+        Family::default()
+    }
+}
+
+impl<L: Clone + Eq + Hash, M: Default> Family<L, M> {
+    /// Returns the child metric for `labels`, creating a zero-valued one on
+    /// first access.
+    pub fn get_or_create(&self, labels: &L) -> &M {
+        let mut children = self.children.lock().unwrap();
+        if !children.contains_key(labels) {
+            children.insert(labels.clone(), Box::default());
+        }
+        let child: *const M = children.get(labels).unwrap().as_ref();
+        // SAFETY: children are boxed and never removed from the map, so the
+        // pointed-to `M` stays at a fixed address for the lifetime of `self`.
+        // Growing the map only moves the `Box` pointers, not their targets.
+        unsafe { &*child }
+    }
+}
+
+impl<L, M: Metric> Family<L, M> {
+    /// Returns the sum of the counts of every child, i.e. the total across all
+    /// partitions.
+    pub fn aggregate(&self) -> usize {
+        self.children
+            .lock()
+            .unwrap()
+            .values()
+            .map(|child| child.count())
+            .sum()
+    }
+}
+
+#[cfg(feature = "metric")]
+impl<L: LabelSet, M: Metric> crate::metric::registry::Encode for Family<L, M> {
+    fn encode(&self, writer: &mut dyn std::io::Write, name: &str) -> std::io::Result<()> {
+        let mut lines: Vec<String> = self
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(labels, child)| {
+                let rendered = labels
+                    .label_pairs()
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{}=\"{}\"",
+                            key,
+                            crate::metric::registry::escape_label_value(value)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{}{{{}}} {}", name, rendered, child.count())
+            })
+            .collect();
+        // Child order in the map is unspecified; sort so the output is stable.
+        lines.sort();
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// A label set that can be rendered as `key="value"` pairs during
+/// serialization.
+#[cfg(feature = "metric")]
+pub trait LabelSet {
+    /// Returns the ordered `(key, value)` pairs describing this label set.
+    fn label_pairs(&self) -> Vec<(&'static str, String)>;
+}
@@ -0,0 +1,115 @@
+// Copyright 2020 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+//! Metrics for non-numeric process facts.
+//!
+//! [`Info`] exposes a set of static key/value labels (for example build and
+//! kernel versions) that always report the value `1`, while [`StateSet`]
+//! exposes one active state out of an enumerated set (for example the VMM
+//! lifecycle state). Both are textual facts that should not be modeled as a
+//! mutable counter, yet still need to appear in the exposition output.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::metric::registry::{escape_label_value, Encode};
+
+/// Renders `(key, value)` label pairs in the `k="v",...` form, escaping each
+/// value per the OpenMetrics text format.
+fn render_labels(labels: &[(String, String)]) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A metric carrying a set of static key/value labels, always valued `1`.
+///
+/// # Example
+/// ```rust
+/// use vmm_sys_util::metric::info::Info;
+/// use vmm_sys_util::metric::registry::Registry;
+///
+/// let build = Info::new(vec![
+///     ("version".to_string(), "1.2".to_string()),
+///     ("kernel".to_string(), "5.15".to_string()),
+/// ]);
+/// let mut registry = Registry::new();
+/// registry.register("vmm_build", None, &build);
+///
+/// let mut out = Vec::new();
+/// registry.encode(&mut out).unwrap();
+/// let expected = "# TYPE vmm_build info\n\
+///     vmm_build{version=\"1.2\",kernel=\"5.15\"} 1\n# EOF\n";
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// ```
+pub struct Info {
+    labels: Vec<(String, String)>,
+}
+
+impl Info {
+    /// Creates an `Info` metric exposing the given label pairs.
+    pub fn new(labels: Vec<(String, String)>) -> Self {
+        Info { labels }
+    }
+}
+
+impl Encode for Info {
+    fn metric_type(&self) -> &str {
+        "info"
+    }
+
+    fn encode(&self, writer: &mut dyn std::io::Write, name: &str) -> std::io::Result<()> {
+        writeln!(writer, "{}{{{}}} 1", name, render_labels(&self.labels))
+    }
+}
+
+/// A metric representing one active state out of an enumerated set.
+///
+/// Each state is emitted as a child sample carrying the state name as a label;
+/// the active state reports `1` and every other state reports `0`.
+pub struct StateSet {
+    label: String,
+    states: Vec<String>,
+    active: AtomicUsize,
+}
+
+impl StateSet {
+    /// Creates a `StateSet` over `states`, keyed by the label `label`, with the
+    /// first state active.
+    pub fn new(label: impl Into<String>, states: Vec<String>) -> Self {
+        StateSet {
+            label: label.into(),
+            states,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// Marks `state` as the active one. Unknown states are ignored.
+    pub fn set(&self, state: &str) {
+        if let Some(index) = self.states.iter().position(|s| s == state) {
+            self.active.store(index, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Encode for StateSet {
+    fn metric_type(&self) -> &str {
+        "stateset"
+    }
+
+    fn encode(&self, writer: &mut dyn std::io::Write, name: &str) -> std::io::Result<()> {
+        let active = self.active.load(Ordering::Relaxed);
+        for (index, state) in self.states.iter().enumerate() {
+            let value = if index == active { 1 } else { 0 };
+            writeln!(
+                writer,
+                "{}{{{}=\"{}\"}} {}",
+                name,
+                self.label,
+                escape_label_value(state),
+                value
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2020 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+//! Collect named metrics and serialize them in a textual exposition format.
+//!
+//! [`Registry`] lets a VMM register its [`Metric`](crate::metric::Metric)
+//! values once, under a name plus optional unit and help text, and later emit
+//! them to any [`Write`] in the OpenMetrics/Prometheus text exposition format.
+//! How an individual metric turns into one or more sample lines is controlled
+//! by the [`Encode`] trait, so downstream crates can teach their own metric
+//! types how to serialize without changing this module.
+
+use std::io::{self, Write};
+use std::sync::atomic::AtomicUsize;
+
+use crate::metric::{AtomicGauge, Gauge, Metric, OrderedMetric};
+
+/// Escapes a label value for the `key="value"` form used throughout the
+/// OpenMetrics text exposition format.
+///
+/// Per the OpenMetrics text format, label values must have backslash, double
+/// quote and line feed escaped; every other byte is passed through as-is.
+pub(crate) fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Serialize the sample value(s) of a metric into an exposition format.
+///
+/// The registry is responsible for the per-metric `# TYPE`/`# HELP` header
+/// block; implementors only write the `name value` sample line(s). A type
+/// implementing `Encode` may emit more than one line (for example a labeled
+/// family) as long as every line is prefixed with `name`.
+pub trait Encode {
+    /// The OpenMetrics metric type reported in the `# TYPE` header, e.g.
+    /// `"counter"` or `"gauge"`.
+    fn metric_type(&self) -> &str {
+        "counter"
+    }
+
+    /// Writes the sample line(s) for this metric under `name` to `writer`.
+    fn encode(&self, writer: &mut dyn Write, name: &str) -> io::Result<()>;
+}
+
+impl Encode for AtomicUsize {
+    fn encode(&self, writer: &mut dyn Write, name: &str) -> io::Result<()> {
+        writeln!(writer, "{} {}", name, self.count())
+    }
+}
+
+impl Encode for () {
+    fn encode(&self, writer: &mut dyn Write, name: &str) -> io::Result<()> {
+        writeln!(writer, "{} {}", name, self.count())
+    }
+}
+
+impl Encode for OrderedMetric {
+    fn encode(&self, writer: &mut dyn Write, name: &str) -> io::Result<()> {
+        writeln!(writer, "{} {}", name, self.count())
+    }
+}
+
+impl Encode for AtomicGauge {
+    fn metric_type(&self) -> &str {
+        "gauge"
+    }
+
+    fn encode(&self, writer: &mut dyn Write, name: &str) -> io::Result<()> {
+        writeln!(writer, "{} {}", name, self.value())
+    }
+}
+
+/// The exposed name of a metric produced by a [`Collector`].
+pub type MetricName = String;
+
+/// A source of metric values that are read on demand rather than accumulated.
+///
+/// Many useful values (host `/proc` counters, cgroup statistics, device driver
+/// counters) are already maintained elsewhere and are monotonic by
+/// construction. Implementing `Collector` lets a component expose the absolute
+/// current value, read by the registry at serialization time, instead of
+/// tracking deltas through `inc`/`add`.
+///
+/// A closure `Fn() -> Vec<(MetricName, usize)>` also implements `Collector`, so
+/// a reader can be registered without defining a dedicated type.
+///
+/// # Example
+/// ```rust
+/// use vmm_sys_util::metric::registry::Registry;
+///
+/// let host_counters = || vec![("host_irqs".to_string(), 42)];
+///
+/// let mut registry = Registry::new();
+/// registry.register_collector(&host_counters);
+///
+/// let mut out = Vec::new();
+/// registry.encode(&mut out).unwrap();
+/// let expected = "# TYPE host_irqs counter\nhost_irqs 42\n# EOF\n";
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// ```
+pub trait Collector {
+    /// Reads the current value of every metric this collector produces.
+    fn collect(&self) -> Vec<(MetricName, usize)>;
+}
+
+impl<F: Fn() -> Vec<(MetricName, usize)>> Collector for F {
+    fn collect(&self) -> Vec<(MetricName, usize)> {
+        self()
+    }
+}
+
+/// A single metric known to a [`Registry`], together with its metadata.
+struct Descriptor<'a> {
+    name: String,
+    unit: Option<String>,
+    help: Option<String>,
+    metric: &'a dyn Encode,
+}
+
+/// A collection of named metrics that can be serialized together.
+///
+/// Metrics are borrowed for the lifetime of the registry, matching the common
+/// case where the counters live for the whole process and the registry is
+/// assembled once at start-up.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::AtomicUsize;
+/// use vmm_sys_util::metric::Metric;
+/// use vmm_sys_util::metric::registry::Registry;
+///
+/// let exits = AtomicUsize::default();
+/// exits.add(3);
+///
+/// let mut registry = Registry::new();
+/// registry.register("vcpu_exits", Some("total number of vCPU exits"), &exits);
+///
+/// let mut out = Vec::new();
+/// registry.encode(&mut out).unwrap();
+/// let expected = "# TYPE vcpu_exits counter\n# HELP vcpu_exits \
+///     total number of vCPU exits\nvcpu_exits 3\n# EOF\n";
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// ```
+#[derive(Default)]
+pub struct Registry<'a> {
+    descriptors: Vec<Descriptor<'a>>,
+    collectors: Vec<&'a dyn Collector>,
+}
+
+impl<'a> Registry<'a> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Registry {
+            descriptors: Vec::new(),
+            collectors: Vec::new(),
+        }
+    }
+
+    /// Registers a [`Collector`] whose values are read every time the registry
+    /// is encoded.
+    pub fn register_collector(&mut self, collector: &'a dyn Collector) {
+        self.collectors.push(collector);
+    }
+
+    /// Registers `metric` under `name` with optional `help` text.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        help: Option<&str>,
+        metric: &'a dyn Encode,
+    ) {
+        self.descriptors.push(Descriptor {
+            name: name.into(),
+            unit: None,
+            help: help.map(String::from),
+            metric,
+        });
+    }
+
+    /// Registers `metric` under `name` with a measurement `unit` (appended to
+    /// the exposed metric name, per OpenMetrics) and optional `help` text.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::sync::atomic::AtomicUsize;
+    /// use vmm_sys_util::metric::Metric;
+    /// use vmm_sys_util::metric::registry::Registry;
+    ///
+    /// let uptime = AtomicUsize::default();
+    /// uptime.add(42);
+    ///
+    /// let mut registry = Registry::new();
+    /// registry.register_with_unit("vmm_uptime", "seconds", Some("VMM process uptime"), &uptime);
+    ///
+    /// let mut out = Vec::new();
+    /// registry.encode(&mut out).unwrap();
+    /// let expected = "# TYPE vmm_uptime_seconds counter\n# HELP vmm_uptime_seconds \
+    ///     VMM process uptime\n# UNIT vmm_uptime_seconds seconds\nvmm_uptime_seconds 42\n# EOF\n";
+    /// assert_eq!(String::from_utf8(out).unwrap(), expected);
+    /// ```
+    pub fn register_with_unit(
+        &mut self,
+        name: impl Into<String>,
+        unit: impl Into<String>,
+        help: Option<&str>,
+        metric: &'a dyn Encode,
+    ) {
+        self.descriptors.push(Descriptor {
+            name: name.into(),
+            unit: Some(unit.into()),
+            help: help.map(String::from),
+            metric,
+        });
+    }
+
+    /// Walks every registered metric and writes its header block and sample
+    /// line(s) to `writer`, terminated by the `# EOF` marker.
+    pub fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+        for descriptor in &self.descriptors {
+            let name = match &descriptor.unit {
+                Some(unit) => format!("{}_{}", descriptor.name, unit),
+                None => descriptor.name.clone(),
+            };
+            writeln!(
+                writer,
+                "# TYPE {} {}",
+                name,
+                descriptor.metric.metric_type()
+            )?;
+            if let Some(help) = &descriptor.help {
+                writeln!(writer, "# HELP {} {}", name, help)?;
+            }
+            if let Some(unit) = &descriptor.unit {
+                writeln!(writer, "# UNIT {} {}", name, unit)?;
+            }
+            descriptor.metric.encode(writer, &name)?;
+        }
+        for collector in &self.collectors {
+            for (name, value) in collector.collect() {
+                writeln!(writer, "# TYPE {} counter", name)?;
+                writeln!(writer, "{} {}", name, value)?;
+            }
+        }
+        writeln!(writer, "# EOF")
+    }
+}
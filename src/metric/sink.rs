@@ -0,0 +1,127 @@
+// Copyright 2020 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR BSD-3-Clause
+//! Push metrics to a StatsD/DogStatsD aggregator.
+//!
+//! Where [`registry`](crate::metric::registry) is pull-based (a scraper asks
+//! for the current exposition), this module is push-based: a [`MetricSink`]
+//! ships individual StatsD lines to an aggregator as they are produced. The
+//! [`StatsdLine`] trait renders a metric into the canonical StatsD form
+//! (`name:value|c` for a counter, `name:value|g` for a gauge), leaving the
+//! trailing newline and any batching to the sink.
+
+use std::io;
+#[cfg(feature = "metric")]
+use std::net::{ToSocketAddrs, UdpSocket};
+
+use crate::metric::{AtomicGauge, Gauge, Metric};
+use std::sync::atomic::AtomicUsize;
+
+/// A destination that accepts rendered StatsD lines.
+///
+/// Implementors decide how lines are delivered (UDP, Unix datagram socket, an
+/// in-memory buffer) and when they are flushed. `emit` takes a single line
+/// without its trailing newline so the sink is free to batch several lines
+/// into one datagram.
+pub trait MetricSink {
+    /// Sends a single rendered line, returning the number of bytes written.
+    fn emit(&self, line: &str) -> io::Result<usize>;
+
+    /// Flushes any buffered lines. The default is a no-op for unbuffered sinks.
+    fn flush(&self) {}
+}
+
+/// A [`MetricSink`] backed by a connected UDP socket.
+#[cfg(feature = "metric")]
+pub struct UdpMetricSink {
+    socket: UdpSocket,
+}
+
+#[cfg(feature = "metric")]
+impl UdpMetricSink {
+    /// Connects `socket` to the aggregator at `sink_addr` and wraps it in a
+    /// sink.
+    pub fn new<A: ToSocketAddrs>(sink_addr: A, socket: UdpSocket) -> io::Result<Self> {
+        socket.connect(sink_addr)?;
+        Ok(UdpMetricSink { socket })
+    }
+}
+
+#[cfg(feature = "metric")]
+impl MetricSink for UdpMetricSink {
+    fn emit(&self, line: &str) -> io::Result<usize> {
+        self.socket.send(line.as_bytes())
+    }
+}
+
+/// A [`MetricSink`] backed by a Unix datagram socket.
+#[cfg(all(feature = "metric", unix))]
+pub struct UnixMetricSink {
+    socket: std::os::unix::net::UnixDatagram,
+    path: std::path::PathBuf,
+}
+
+#[cfg(all(feature = "metric", unix))]
+impl UnixMetricSink {
+    /// Wraps `socket`, sending every line to the aggregator listening on
+    /// `path`.
+    pub fn new<P: Into<std::path::PathBuf>>(
+        path: P,
+        socket: std::os::unix::net::UnixDatagram,
+    ) -> Self {
+        UnixMetricSink {
+            socket,
+            path: path.into(),
+        }
+    }
+}
+
+#[cfg(all(feature = "metric", unix))]
+impl MetricSink for UnixMetricSink {
+    fn emit(&self, line: &str) -> io::Result<usize> {
+        self.socket.send_to(line.as_bytes(), &self.path)
+    }
+}
+
+/// A [`MetricSink`] that discards everything, for no-metric builds.
+pub struct NopSink;
+
+impl MetricSink for NopSink {
+    fn emit(&self, _: &str) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+/// Renders a metric into a canonical StatsD line, without a trailing newline.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::AtomicUsize;
+/// use vmm_sys_util::metric::Metric;
+/// use vmm_sys_util::metric::sink::StatsdLine;
+///
+/// let exits = AtomicUsize::default();
+/// exits.add(7);
+/// assert_eq!(exits.statsd_line("vcpu_exits"), "vcpu_exits:7|c");
+/// ```
+pub trait StatsdLine {
+    /// Returns the StatsD line for this metric exposed under `name`.
+    fn statsd_line(&self, name: &str) -> String;
+}
+
+impl StatsdLine for AtomicUsize {
+    fn statsd_line(&self, name: &str) -> String {
+        format!("{}:{}|c", name, self.count())
+    }
+}
+
+impl StatsdLine for () {
+    fn statsd_line(&self, name: &str) -> String {
+        format!("{}:{}|c", name, self.count())
+    }
+}
+
+impl StatsdLine for AtomicGauge {
+    fn statsd_line(&self, name: &str) -> String {
+        format!("{}:{}|g", name, self.value())
+    }
+}